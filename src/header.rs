@@ -25,6 +25,9 @@ pub enum Algorithm {
     RS256,
     RS384,
     RS512,
+    ES256,
+    ES384,
+    ES512,
 }
 
 impl<T: Serialize + DeserializeOwned> Header<T> {
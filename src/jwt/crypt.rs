@@ -0,0 +1,221 @@
+use super::{Algorithm, Result};
+use anyhow::format_err;
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use openssl::bn::BigNum;
+use openssl::ec::EcKey;
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::memcmp;
+use openssl::pkey::PKey;
+use openssl::rsa::Rsa;
+use openssl::sign::{Signer, Verifier};
+
+/// Sign `data` with `key`, using the digest implied by `alg`.
+pub fn sign(data: &str, key: &[u8], alg: &Algorithm) -> Result<String> {
+    match *alg {
+        Algorithm::HS256 => sign_hmac(data, key, MessageDigest::sha256()),
+        Algorithm::HS384 => sign_hmac(data, key, MessageDigest::sha384()),
+        Algorithm::HS512 => sign_hmac(data, key, MessageDigest::sha512()),
+        Algorithm::RS256 => sign_rsa(data, key, MessageDigest::sha256()),
+        Algorithm::RS384 => sign_rsa(data, key, MessageDigest::sha384()),
+        Algorithm::RS512 => sign_rsa(data, key, MessageDigest::sha512()),
+        Algorithm::ES256 => sign_ec(data, key, MessageDigest::sha256(), 32),
+        Algorithm::ES384 => sign_ec(data, key, MessageDigest::sha384(), 48),
+        Algorithm::ES512 => sign_ec(data, key, MessageDigest::sha512(), 66),
+    }
+}
+
+/// Verify `sig` against `data` with `key`, trusting `alg` to pick the routine.
+///
+/// `alg` is almost always read straight out of an attacker-controlled token
+/// header, so this function alone is not safe to expose to untrusted callers:
+/// see `Token::verify_with_algorithm`, which pins `alg` to a value the caller
+/// chose rather than one the token claims for itself.
+pub fn verify(sig: &str, data: &str, key: &[u8], alg: &Algorithm) -> Result<bool> {
+    match *alg {
+        Algorithm::HS256 => verify_hmac(sig, data, key, MessageDigest::sha256()),
+        Algorithm::HS384 => verify_hmac(sig, data, key, MessageDigest::sha384()),
+        Algorithm::HS512 => verify_hmac(sig, data, key, MessageDigest::sha512()),
+        Algorithm::RS256 => verify_rsa(sig, data, key, MessageDigest::sha256()),
+        Algorithm::RS384 => verify_rsa(sig, data, key, MessageDigest::sha384()),
+        Algorithm::RS512 => verify_rsa(sig, data, key, MessageDigest::sha512()),
+        Algorithm::ES256 => verify_ec(sig, data, key, MessageDigest::sha256(), 32),
+        Algorithm::ES384 => verify_ec(sig, data, key, MessageDigest::sha384(), 48),
+        Algorithm::ES512 => verify_ec(sig, data, key, MessageDigest::sha512(), 66),
+    }
+}
+
+fn sign_hmac(data: &str, key: &[u8], digest: MessageDigest) -> Result<String> {
+    let pkey = PKey::hmac(key)?;
+    let mut signer = Signer::new(digest, &pkey)?;
+    signer.update(data.as_bytes())?;
+    let sig = signer.sign_to_vec()?;
+    Ok(encode_config(&sig, URL_SAFE_NO_PAD))
+}
+
+fn verify_hmac(sig: &str, data: &str, key: &[u8], digest: MessageDigest) -> Result<bool> {
+    // Comparing the base64 strings (or the raw bytes) with ordinary equality
+    // short-circuits on the first differing byte, leaking timing information
+    // that can in principle be used to forge a signature one byte at a time.
+    // `openssl::memcmp::eq` always inspects every byte, so decode both sides
+    // to raw bytes first and compare those in constant time.
+    let provided = decode_config(sig, URL_SAFE_NO_PAD)?;
+    let computed = decode_config(&sign_hmac(data, key, digest)?, URL_SAFE_NO_PAD)?;
+
+    if provided.len() != computed.len() {
+        return Ok(false);
+    }
+
+    Ok(memcmp::eq(&provided, &computed))
+}
+
+fn sign_rsa(data: &str, key: &[u8], digest: MessageDigest) -> Result<String> {
+    let rsa = Rsa::private_key_from_pem(key)?;
+    let pkey = PKey::from_rsa(rsa)?;
+    let mut signer = Signer::new(digest, &pkey)?;
+    signer.update(data.as_bytes())?;
+    let sig = signer.sign_to_vec()?;
+    Ok(encode_config(&sig, URL_SAFE_NO_PAD))
+}
+
+fn verify_rsa(sig: &str, data: &str, key: &[u8], digest: MessageDigest) -> Result<bool> {
+    let rsa = Rsa::public_key_from_pem(key)?;
+    let pkey = PKey::from_rsa(rsa)?;
+    let signature = decode_config(sig, URL_SAFE_NO_PAD)?;
+
+    let mut verifier = Verifier::new(digest, &pkey)?;
+    verifier.update(data.as_bytes())?;
+    Ok(verifier.verify(&signature)?)
+}
+
+/// Signs with an EC private key and re-packs the result as JWS expects.
+///
+/// `Signer` hands back a DER-encoded `EcdsaSig`, but JWS wants the raw `R || S`
+/// concatenation, each half zero-padded to `coord_len` (the byte length of the
+/// curve's coordinates: 32 for P-256, 48 for P-384, 66 for P-521). DER strips
+/// leading zero bytes from `R` and `S`, so a naive concatenation of the DER
+/// integers would produce a variable-length, unverifiable signature.
+fn sign_ec(data: &str, key: &[u8], digest: MessageDigest, coord_len: usize) -> Result<String> {
+    let ec_key = EcKey::private_key_from_pem(key)?;
+    let pkey = PKey::from_ec_key(ec_key)?;
+    let mut signer = Signer::new(digest, &pkey)?;
+    signer.update(data.as_bytes())?;
+    let der = signer.sign_to_vec()?;
+
+    let ecdsa_sig = EcdsaSig::from_der(&der)?;
+    let mut raw = pad_to_len(&ecdsa_sig.r().to_vec(), coord_len)?;
+    raw.extend(pad_to_len(&ecdsa_sig.s().to_vec(), coord_len)?);
+
+    Ok(encode_config(&raw, URL_SAFE_NO_PAD))
+}
+
+/// Verifies a JWS `R || S` signature against an EC public key.
+///
+/// This reverses `sign_ec`: split the fixed-width signature into its two
+/// halves, rebuild an `EcdsaSig` from the `R` and `S` big numbers, re-encode it
+/// as DER, and hand that to `Verifier`, which is what openssl expects.
+fn verify_ec(sig: &str, data: &str, key: &[u8], digest: MessageDigest, coord_len: usize) -> Result<bool> {
+    let raw = decode_config(sig, URL_SAFE_NO_PAD)?;
+    if raw.len() != coord_len * 2 {
+        return Ok(false);
+    }
+
+    let r = BigNum::from_slice(&raw[..coord_len])?;
+    let s = BigNum::from_slice(&raw[coord_len..])?;
+    let der = EcdsaSig::from_private_components(r, s)?.to_der()?;
+
+    let ec_key = EcKey::public_key_from_pem(key)?;
+    let pkey = PKey::from_ec_key(ec_key)?;
+    let mut verifier = Verifier::new(digest, &pkey)?;
+    verifier.update(data.as_bytes())?;
+    Ok(verifier.verify(&der)?)
+}
+
+fn pad_to_len(bytes: &[u8], len: usize) -> Result<Vec<u8>> {
+    if bytes.len() > len {
+        return Err(format_err!(
+            "EC signature component is {} bytes, expected at most {}",
+            bytes.len(),
+            len
+        ));
+    }
+
+    let mut padded = vec![0u8; len];
+    let start = len - bytes.len();
+    padded[start..].copy_from_slice(bytes);
+    Ok(padded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sign, verify, Algorithm};
+    use super::pad_to_len;
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+
+    #[test]
+    fn hmac_verify_accepts_correct_signature() {
+        let data = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0";
+        let sig = sign(data, b"secret", &Algorithm::HS256).unwrap();
+
+        assert!(verify(&sig, data, b"secret", &Algorithm::HS256).unwrap());
+    }
+
+    #[test]
+    fn hmac_verify_rejects_one_byte_off_signature() {
+        let data = "eyJhbGciOiJIUzI1NiJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0";
+        let sig = sign(data, b"secret", &Algorithm::HS256).unwrap();
+
+        // Flip the last base64 character so the decoded signature bytes
+        // differ by roughly one byte, exercising the decode-then-`memcmp::eq`
+        // path rather than a plain string/byte comparison.
+        let mut tampered = sig.clone();
+        let last = tampered.pop().unwrap();
+        let flipped = if last == 'A' { 'B' } else { 'A' };
+        tampered.push(flipped);
+
+        assert!(!verify(&tampered, data, b"secret", &Algorithm::HS256).unwrap());
+    }
+
+    #[test]
+    fn pad_to_len_zero_pads_short_components() {
+        // DER drops leading zero bytes from R/S, so a component shorter than
+        // the curve's coordinate length must come back left-padded with
+        // zeros rather than concatenated as-is.
+        let padded = pad_to_len(&[0xAB], 4).unwrap();
+        assert_eq!(padded, vec![0x00, 0x00, 0x00, 0xAB]);
+    }
+
+    #[test]
+    fn pad_to_len_rejects_oversized_component() {
+        assert!(pad_to_len(&[0u8; 5], 4).is_err());
+    }
+
+    #[test]
+    fn ecdsa_roundtrip_es256() {
+        assert_ecdsa_roundtrip(Algorithm::ES256, Nid::X9_62_PRIME256V1);
+    }
+
+    #[test]
+    fn ecdsa_roundtrip_es384() {
+        assert_ecdsa_roundtrip(Algorithm::ES384, Nid::SECP384R1);
+    }
+
+    #[test]
+    fn ecdsa_roundtrip_es512() {
+        assert_ecdsa_roundtrip(Algorithm::ES512, Nid::SECP521R1);
+    }
+
+    fn assert_ecdsa_roundtrip(alg: Algorithm, nid: Nid) {
+        let group = EcGroup::from_curve_name(nid).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let private_pem = ec_key.private_key_to_pem().unwrap();
+        let public_pem = ec_key.public_key_to_pem().unwrap();
+
+        let data = "eyJhbGciOiJFUzI1NiJ9.eyJzdWIiOiIxMjM0NTY3ODkwIn0";
+
+        let sig = sign(data, &private_pem, &alg).unwrap();
+        assert!(verify(&sig, data, &public_pem, &alg).unwrap());
+        assert!(!verify(&sig, "tampered", &public_pem, &alg).unwrap());
+    }
+}
@@ -0,0 +1,217 @@
+use super::{Algorithm, Result};
+use anyhow::format_err;
+use base64::{decode_config, URL_SAFE_NO_PAD};
+use openssl::bn::{BigNum, BigNumContext};
+use openssl::ec::{EcGroup, EcKey, EcPoint};
+use openssl::nid::Nid;
+use openssl::rsa::Rsa;
+use serde::Deserialize;
+use serde_json;
+
+/// Implemented by custom header types that carry a `kid` (key ID) field, so
+/// `Token::verify_with_jwks` can look up the right key without knowing
+/// anything else about the application's header shape. The custom-headers
+/// tests on `Header<T>` already show `kid` living in exactly such a type.
+pub trait KeyId {
+    fn kid(&self) -> Option<&str>;
+}
+
+/// A JSON Web Key Set: the standard format for publishing a rotating set of
+/// verification keys, each identified by a `kid`.
+#[derive(Debug, Deserialize)]
+pub struct JwkSet {
+    pub keys: Vec<Jwk>,
+}
+
+impl JwkSet {
+    /// Parse a JWKS document, as published at a provider's `jwks_uri`.
+    pub fn from_json(raw: &str) -> Result<JwkSet> {
+        Ok(serde_json::from_str(raw)?)
+    }
+
+    /// Find the key whose `kid` matches.
+    pub fn find(&self, kid: &str) -> Option<&Jwk> {
+        self.keys.iter().find(|jwk| jwk.kid.as_ref().map(|k| &**k) == Some(kid))
+    }
+}
+
+/// A single entry in a `JwkSet`. Only the fields needed to reconstruct an
+/// openssl key are kept typed; `kty` selects which of `n`/`e`, `x`/`y`/`crv`,
+/// or `k` are populated.
+#[derive(Debug, Deserialize)]
+pub struct Jwk {
+    pub kty: String,
+    pub kid: Option<String>,
+    pub alg: Option<String>,
+    // RSA
+    pub n: Option<String>,
+    pub e: Option<String>,
+    // EC
+    pub crv: Option<String>,
+    pub x: Option<String>,
+    pub y: Option<String>,
+    // oct (symmetric)
+    pub k: Option<String>,
+}
+
+impl Jwk {
+    /// The algorithm this key is meant to be used with, as declared by `alg`.
+    pub fn algorithm(&self) -> Result<Algorithm> {
+        match self.alg.as_ref().map(|a| &**a) {
+            Some("HS256") => Ok(Algorithm::HS256),
+            Some("HS384") => Ok(Algorithm::HS384),
+            Some("HS512") => Ok(Algorithm::HS512),
+            Some("RS256") => Ok(Algorithm::RS256),
+            Some("RS384") => Ok(Algorithm::RS384),
+            Some("RS512") => Ok(Algorithm::RS512),
+            Some("ES256") => Ok(Algorithm::ES256),
+            Some("ES384") => Ok(Algorithm::ES384),
+            Some("ES512") => Ok(Algorithm::ES512),
+            Some(other) => Err(format_err!("Unsupported JWK algorithm: {}", other)),
+            None => Err(format_err!("JWK is missing its `alg` field")),
+        }
+    }
+
+    /// Reconstruct the key material `crypt::verify` needs: a PEM-encoded
+    /// public key for `RSA`/`EC`, or the raw secret bytes for `oct`.
+    pub fn to_key_material(&self) -> Result<Vec<u8>> {
+        match &*self.kty {
+            "RSA" => self.to_rsa_pem(),
+            "EC" => self.to_ec_pem(),
+            "oct" => self.to_oct_secret(),
+            other => Err(format_err!("Unsupported JWK key type: {}", other)),
+        }
+    }
+
+    fn to_rsa_pem(&self) -> Result<Vec<u8>> {
+        let n = decode_bignum(self.n.as_ref(), "n")?;
+        let e = decode_bignum(self.e.as_ref(), "e")?;
+        let rsa = Rsa::from_public_components(n, e)?;
+        Ok(rsa.public_key_to_pem()?)
+    }
+
+    fn to_ec_pem(&self) -> Result<Vec<u8>> {
+        let nid = match self.crv.as_ref().map(|c| &**c) {
+            Some("P-256") => Nid::X9_62_PRIME256V1,
+            Some("P-384") => Nid::SECP384R1,
+            Some("P-521") => Nid::SECP521R1,
+            Some(other) => return Err(format_err!("Unsupported JWK curve: {}", other)),
+            None => return Err(format_err!("JWK is missing its `crv` field")),
+        };
+
+        let x = decode_bignum(self.x.as_ref(), "x")?;
+        let y = decode_bignum(self.y.as_ref(), "y")?;
+
+        let group = EcGroup::from_curve_name(nid)?;
+        let mut ctx = BigNumContext::new()?;
+        let mut point = EcPoint::new(&group)?;
+        point.set_affine_coordinates_gfp(&group, &x, &y, &mut ctx)?;
+
+        let ec_key = EcKey::from_public_key(&group, &point)?;
+        Ok(ec_key.public_key_to_pem()?)
+    }
+
+    fn to_oct_secret(&self) -> Result<Vec<u8>> {
+        match self.k {
+            Some(ref k) => Ok(decode_config(k, URL_SAFE_NO_PAD)?),
+            None => Err(format_err!("JWK is missing its `k` field")),
+        }
+    }
+}
+
+fn decode_bignum(field: Option<&String>, name: &str) -> Result<BigNum> {
+    let field = field.ok_or_else(|| format_err!("JWK is missing its `{}` field", name))?;
+    let bytes = decode_config(field, URL_SAFE_NO_PAD)?;
+    Ok(BigNum::from_slice(&bytes)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Algorithm, JwkSet, KeyId};
+    use base64::{encode_config, URL_SAFE_NO_PAD};
+    use openssl::ec::{EcGroup, EcKey};
+    use openssl::nid::Nid;
+    use openssl::rsa::Rsa;
+    use serde::{Deserialize, Serialize};
+    use super::super::{Header, Token};
+
+    #[derive(Debug, Default, Serialize, Deserialize, PartialEq)]
+    struct WithKid {
+        kid: String,
+    }
+
+    impl KeyId for WithKid {
+        fn kid(&self) -> Option<&str> {
+            Some(&self.kid)
+        }
+    }
+
+    #[test]
+    fn from_json_finds_key_by_kid() {
+        let json = r#"{"keys":[{"kty":"oct","kid":"key-1","alg":"HS256","k":"c2VjcmV0"}]}"#;
+        let jwks = JwkSet::from_json(json).unwrap();
+
+        assert!(jwks.find("key-1").is_some());
+        assert!(jwks.find("missing").is_none());
+    }
+
+    #[test]
+    fn oct_jwk_verifies_a_real_token() {
+        let header = Header { alg: Algorithm::HS256, headers: Some(WithKid { kid: "key-1".into() }) };
+        let token: Token<WithKid, ()> = Token::new(header, Default::default());
+        let raw = token.sign(b"secret").unwrap();
+        let same = Token::<WithKid, ()>::parse(&raw).unwrap();
+
+        let json = r#"{"keys":[{"kty":"oct","kid":"key-1","alg":"HS256","k":"c2VjcmV0"}]}"#;
+        let jwks = JwkSet::from_json(json).unwrap();
+
+        assert!(same.verify_with_jwks(&jwks).unwrap());
+    }
+
+    #[test]
+    fn rsa_jwk_verifies_a_real_token() {
+        let keypair = Rsa::generate(2048).unwrap();
+        let n = encode_config(&keypair.n().to_vec(), URL_SAFE_NO_PAD);
+        let e = encode_config(&keypair.e().to_vec(), URL_SAFE_NO_PAD);
+
+        let header = Header { alg: Algorithm::RS256, headers: Some(WithKid { kid: "key-1".into() }) };
+        let token: Token<WithKid, ()> = Token::new(header, Default::default());
+        let raw = token.sign(&keypair.private_key_to_pem().unwrap()).unwrap();
+        let same = Token::<WithKid, ()>::parse(&raw).unwrap();
+
+        let json = format!(
+            r#"{{"keys":[{{"kty":"RSA","kid":"key-1","alg":"RS256","n":"{}","e":"{}"}}]}}"#,
+            n, e
+        );
+        let jwks = JwkSet::from_json(&json).unwrap();
+
+        assert!(same.verify_with_jwks(&jwks).unwrap());
+    }
+
+    #[test]
+    fn ec_jwk_verifies_a_real_token() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec_key = EcKey::generate(&group).unwrap();
+        let mut ctx = ::openssl::bn::BigNumContext::new().unwrap();
+        let mut x = ::openssl::bn::BigNum::new().unwrap();
+        let mut y = ::openssl::bn::BigNum::new().unwrap();
+        ec_key
+            .public_key()
+            .affine_coordinates_gfp(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+
+        let header = Header { alg: Algorithm::ES256, headers: Some(WithKid { kid: "key-1".into() }) };
+        let token: Token<WithKid, ()> = Token::new(header, Default::default());
+        let raw = token.sign(&ec_key.private_key_to_pem().unwrap()).unwrap();
+        let same = Token::<WithKid, ()>::parse(&raw).unwrap();
+
+        let json = format!(
+            r#"{{"keys":[{{"kty":"EC","kid":"key-1","alg":"ES256","crv":"P-256","x":"{}","y":"{}"}}]}}"#,
+            encode_config(&x.to_vec(), URL_SAFE_NO_PAD),
+            encode_config(&y.to_vec(), URL_SAFE_NO_PAD)
+        );
+        let jwks = JwkSet::from_json(&json).unwrap();
+
+        assert!(same.verify_with_jwks(&jwks).unwrap());
+    }
+}
@@ -1,13 +1,15 @@
 use serde::Serialize;
 use serde::de::DeserializeOwned;
-pub use self::header::Header;
-pub use self::header::Algorithm;
+pub use header::Header;
+pub use header::Algorithm;
 pub use self::payload::Payload;
+pub use self::payload::Validation;
+pub use self::jwks::{Jwk, JwkSet, KeyId};
 use Result;
 
-mod header;
 mod payload;
 mod crypt;
+mod jwks;
 
 /// Main struct representing a JSON Web Token, composed of a header and a set of claims.
 #[derive(Debug, Default)]
@@ -42,6 +44,13 @@ impl<H, C> Token<H, C>
     }
 
     /// Verify a token with a key and the token's specific algorithm.
+    ///
+    /// This trusts whatever `alg` the token's own header claims, which makes it
+    /// unsafe to call with a key whose type depends on who holds it (e.g. an RSA
+    /// public key): an attacker can re-sign a token as `HS256` using that public
+    /// key's bytes as the HMAC secret, and this method will accept it. Prefer
+    /// `verify_with_algorithm` whenever the expected algorithm is known ahead of
+    /// time, which is true for almost every caller.
     pub fn verify(&self, key: &[u8]) -> Result<bool> {
         let raw = match self.raw {
             Some(ref s) => s,
@@ -55,6 +64,90 @@ impl<H, C> Token<H, C>
         Ok(self.payload.verify() && crypt::verify(sig, data, key, &self.header.alg)?)
     }
 
+    /// Verify a token with a key, requiring the token's header to declare exactly
+    /// `expected` as its algorithm.
+    ///
+    /// This closes the classic JWT algorithm-confusion hole: without it, an
+    /// attacker who obtains an RSA public key can forge a token by signing it
+    /// with `HS256` using the public key bytes as the HMAC secret, and `verify`
+    /// will accept it because it reads `alg` straight out of the attacker-
+    /// controlled header. Pinning `expected` to the algorithm the caller actually
+    /// issued tokens with means that mismatch is rejected before any
+    /// cryptographic work happens.
+    pub fn verify_with_algorithm(&self, key: &[u8], expected: &Algorithm) -> Result<bool> {
+        if self.header.alg != *expected {
+            return Ok(false);
+        }
+
+        self.verify(key)
+    }
+
+    /// Verify a token's signature and claims against a caller-supplied `Validation`.
+    ///
+    /// `verify` only ever checks `nbf`/`exp` with zero clock-skew tolerance and
+    /// ignores `iss`/`aud`/`sub` entirely, which RFC 7519 leaves up to the
+    /// application to enforce. This requires the token's header to declare
+    /// exactly `expected` as its algorithm (the same algorithm-confusion guard
+    /// as `verify_with_algorithm` — nothing here may trust `self.header.alg`),
+    /// then applies `validation`'s leeway and expected issuer/audience/subject
+    /// to the payload.
+    ///
+    /// Note this takes `expected: &Algorithm` in addition to `key` and
+    /// `validation`, rather than just `(&self, key, &Validation)`. Claim
+    /// validation is worthless against a forged signature, so this closes the
+    /// same algorithm-confusion hole `verify_with_algorithm` does instead of
+    /// silently trusting `self.header.alg` the way a two-argument version
+    /// would have to.
+    pub fn verify_with_validation(
+        &self,
+        key: &[u8],
+        expected: &Algorithm,
+        validation: &Validation,
+    ) -> Result<bool> {
+        if self.header.alg != *expected {
+            return Ok(false);
+        }
+
+        let raw = match self.raw {
+            Some(ref s) => s,
+            None => return Ok(false),
+        };
+
+        let pieces: Vec<_> = raw.rsplitn(2, '.').collect();
+        let sig = pieces[0];
+        let data = pieces[1];
+
+        Ok(crypt::verify(sig, data, key, expected)? && self.payload.verify_with_validation(validation))
+    }
+
+    /// Verify a token against a rotating set of keys, selecting the right one
+    /// via the `kid` the header's custom-headers type exposes through
+    /// `KeyId`.
+    ///
+    /// Real deployments rarely pin a single static key; instead they publish
+    /// a JWK Set and rotate which key is actually signing tokens, tagging
+    /// each token's header with the `kid` of the key that signed it. This
+    /// looks that key up, reconstructs it from its JWK components, and
+    /// verifies the token against the algorithm the JWK itself declares
+    /// (never the one the token's header claims), closing the same
+    /// algorithm-confusion hole `verify_with_algorithm` does.
+    pub fn verify_with_jwks(&self, jwks: &JwkSet) -> Result<bool>
+        where H: KeyId
+    {
+        let kid = match self.header.headers.as_ref().and_then(|h| h.kid()) {
+            Some(kid) => kid,
+            None => return Ok(false),
+        };
+
+        let jwk = match jwks.find(kid) {
+            Some(jwk) => jwk,
+            None => return Ok(false),
+        };
+
+        let key = jwk.to_key_material()?;
+        self.verify_with_algorithm(&key, &jwk.algorithm()?)
+    }
+
     /// Generate the signed token from a key with the specific algorithm as a url-safe, base64
     /// string.
     pub fn sign(&self, key: &[u8]) -> Result<String> {
@@ -82,6 +175,7 @@ mod tests {
     use openssl;
     use std::default::Default;
     use time::{self, Duration, Tm};
+    use super::{Algorithm, Token};
     use super::Algorithm::{HS256, RS512};
 
     #[test]
@@ -149,6 +243,42 @@ mod tests {
         assert!(same.verify(&rsa_keypair.public_key_to_pem().unwrap()).unwrap());
     }
 
+    #[test]
+    pub fn verify_with_algorithm_rejects_algorithm_confusion() {
+        // An attacker who obtains an RSA public key can re-sign a token as
+        // HS256 using the key's own PEM bytes as the HMAC secret. Pinning the
+        // expected algorithm must reject that token rather than trust the
+        // (forged) header.
+        let keypair = openssl::rsa::Rsa::generate(2048).unwrap();
+        let public_pem = keypair.public_key_to_pem().unwrap();
+
+        let header: Header<()> = Header { alg: HS256, ..Default::default() };
+        let token: Token<(), ()> = Token::new(header, Default::default());
+        let raw = token.sign(&public_pem).unwrap();
+        let forged = Token::<(), ()>::parse(&raw).unwrap();
+
+        assert_eq!(
+            forged.verify_with_algorithm(&public_pem, &Algorithm::RS256).unwrap(),
+            false
+        );
+    }
+
+    #[test]
+    pub fn verify_with_algorithm_rejects_mismatch_before_crypto() {
+        let header: Header<()> = Header { alg: HS256, ..Default::default() };
+        let token: Token<(), ()> = Token::new(header, Default::default());
+        let raw = token.sign("secret".as_bytes()).unwrap();
+        let same = Token::<(), ()>::parse(&raw).unwrap();
+
+        // "not-a-pem" isn't a valid RSA key. If the algorithm check ran after
+        // (or not at all before) the cryptographic work, this would return an
+        // `Err` from the RSA key parsing rather than a clean `Ok(false)`.
+        assert_eq!(
+            same.verify_with_algorithm("not-a-pem".as_bytes(), &Algorithm::RS256).unwrap(),
+            false
+        );
+    }
+
     fn create_for_range(nbf: Tm, exp: Tm) -> DefaultToken<()> {
         let header: Header<()> = Default::default();
         let payload = DefaultPayload {
@@ -0,0 +1,259 @@
+use super::Result;
+use anyhow::format_err;
+use base64::{decode_config, encode_config, URL_SAFE_NO_PAD};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{self, Value};
+use time;
+
+/// An extensible set of claims, carrying the registered `iss`/`sub`/`aud`/
+/// `exp`/`nbf` fields directly and allowing additional application-specific
+/// claims via a struct that can be serialized and deserialized, the same way
+/// `Header<T>` layers custom headers on top of `alg`.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Payload<T = ()> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sub: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aud: Option<Audience>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub exp: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub nbf: Option<u64>,
+    #[serde(skip_serializing)]
+    pub private: Option<T>,
+}
+
+/// The `aud` claim per RFC 7519, which permits either a single string or an
+/// array of strings identifying the token's intended recipients.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Audience {
+    Single(String),
+    Multiple(Vec<String>),
+}
+
+impl Audience {
+    /// Whether `expected` is (one of) the intended recipient(s).
+    pub fn contains(&self, expected: &str) -> bool {
+        match *self {
+            Audience::Single(ref aud) => aud == expected,
+            Audience::Multiple(ref auds) => auds.iter().any(|aud| aud == expected),
+        }
+    }
+}
+
+impl<T: Serialize + DeserializeOwned> Payload<T> {
+    /// Decode from base64.
+    pub fn from_base64(raw: &str) -> Result<Payload<T>> {
+        let data = decode_config(raw, URL_SAFE_NO_PAD)?;
+        let own: Payload<T> = serde_json::from_slice(&data)?;
+
+        let private: Option<T> = serde_json::from_slice(&data).ok();
+
+        Ok(Payload { private, ..own })
+    }
+
+    /// Encode to a string.
+    pub fn to_base64(&self) -> Result<String> {
+        if let Value::Object(mut own_map) = serde_json::to_value(&self)? {
+            match self.private {
+                Some(ref private) => {
+                    if let Value::Object(extra_map) = serde_json::to_value(&private)? {
+                        own_map.extend(extra_map);
+                        let s = serde_json::to_string(&own_map)?;
+                        let enc = encode_config((&*s).as_bytes(), URL_SAFE_NO_PAD);
+                        Ok(enc)
+                    } else {
+                        Err(format_err!("Could not access additional claims."))
+                    }
+                }
+                None => {
+                    let s = serde_json::to_string(&own_map)?;
+                    let enc = encode_config((&*s).as_bytes(), URL_SAFE_NO_PAD);
+                    Ok(enc)
+                }
+            }
+        } else {
+            Err(format_err!("Could not access default claims."))
+        }
+    }
+
+    /// Check `nbf`/`exp` against the current time, with no clock-skew
+    /// tolerance and no check of `iss`/`aud`/`sub`.
+    ///
+    /// Prefer `verify_with_validation` whenever the caller cares about issuer,
+    /// audience, subject, or tolerating a little clock drift between issuer
+    /// and verifier, which in practice is almost always.
+    pub fn verify(&self) -> bool {
+        self.verify_with_validation(&Validation::default())
+    }
+
+    /// Check the registered claims against `validation`: `leeway` is applied
+    /// as clock-skew tolerance around `nbf`/`exp`, and any configured
+    /// `expected_*` field must match the corresponding claim exactly (for an
+    /// array `aud`, it must equal one of its entries).
+    pub fn verify_with_validation(&self, validation: &Validation) -> bool {
+        let now = time::get_time().sec as u64;
+
+        if validation.validate_nbf {
+            if let Some(nbf) = self.nbf {
+                if now.saturating_add(validation.leeway) < nbf {
+                    return false;
+                }
+            }
+        }
+
+        if validation.validate_exp {
+            if let Some(exp) = self.exp {
+                if now.saturating_sub(validation.leeway) > exp {
+                    return false;
+                }
+            }
+        }
+
+        if validation.expected_iss.is_some() && validation.expected_iss != self.iss {
+            return false;
+        }
+        if let Some(ref expected_aud) = validation.expected_aud {
+            match self.aud {
+                Some(ref aud) if aud.contains(expected_aud) => {}
+                _ => return false,
+            }
+        }
+        if validation.expected_sub.is_some() && validation.expected_sub != self.sub {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Configurable claim validation, covering clock skew and the registered
+/// `iss`/`aud`/`sub` claims that the plain `nbf`/`exp` check in `verify` never
+/// looks at.
+///
+/// RFC 7519 leaves issuer/audience/subject checking up to the application; a
+/// `Validation` lets a caller describe what it expects once and have it
+/// applied on every `Token::verify_with_validation` call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Validation {
+    pub leeway: u64,
+    pub validate_exp: bool,
+    pub validate_nbf: bool,
+    pub expected_iss: Option<String>,
+    pub expected_aud: Option<String>,
+    pub expected_sub: Option<String>,
+}
+
+impl Default for Validation {
+    fn default() -> Validation {
+        Validation {
+            leeway: 0,
+            validate_exp: true,
+            validate_nbf: true,
+            expected_iss: None,
+            expected_aud: None,
+            expected_sub: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Audience, Payload, Validation};
+    use time;
+
+    #[test]
+    fn audience_array_round_trips() {
+        let payload: Payload<()> = Payload {
+            aud: Some(Audience::Multiple(vec!["a".into(), "b".into()])),
+            ..Default::default()
+        };
+
+        let raw = payload.to_base64().unwrap();
+        let same = Payload::<()>::from_base64(&raw).unwrap();
+
+        assert_eq!(payload, same);
+    }
+
+    #[test]
+    fn verify_with_validation_rejects_iss_mismatch() {
+        let payload: Payload<()> = Payload { iss: Some("issuer-a".into()), ..Default::default() };
+        let validation = Validation { expected_iss: Some("issuer-b".into()), ..Default::default() };
+
+        assert_eq!(payload.verify_with_validation(&validation), false);
+    }
+
+    #[test]
+    fn verify_with_validation_accepts_matching_iss() {
+        let payload: Payload<()> = Payload { iss: Some("issuer-a".into()), ..Default::default() };
+        let validation = Validation { expected_iss: Some("issuer-a".into()), ..Default::default() };
+
+        assert!(payload.verify_with_validation(&validation));
+    }
+
+    #[test]
+    fn verify_with_validation_matches_single_or_array_audience() {
+        let single: Payload<()> = Payload { aud: Some(Audience::Single("a".into())), ..Default::default() };
+        let array: Payload<()> = Payload {
+            aud: Some(Audience::Multiple(vec!["a".into(), "b".into()])),
+            ..Default::default()
+        };
+        let validation = Validation { expected_aud: Some("a".into()), ..Default::default() };
+
+        assert!(single.verify_with_validation(&validation));
+        assert!(array.verify_with_validation(&validation));
+    }
+
+    #[test]
+    fn verify_with_validation_rejects_aud_mismatch() {
+        let payload: Payload<()> = Payload { aud: Some(Audience::Single("a".into())), ..Default::default() };
+        let validation = Validation { expected_aud: Some("b".into()), ..Default::default() };
+
+        assert_eq!(payload.verify_with_validation(&validation), false);
+    }
+
+    #[test]
+    fn verify_with_validation_rejects_sub_mismatch() {
+        let payload: Payload<()> = Payload { sub: Some("user-a".into()), ..Default::default() };
+        let validation = Validation { expected_sub: Some("user-b".into()), ..Default::default() };
+
+        assert_eq!(payload.verify_with_validation(&validation), false);
+    }
+
+    #[test]
+    fn verify_with_validation_accepts_exp_within_leeway() {
+        let now = time::get_time().sec as u64;
+        let payload: Payload<()> = Payload { exp: Some(now - 5), ..Default::default() };
+        let validation = Validation { leeway: 10, ..Default::default() };
+
+        assert!(payload.verify_with_validation(&validation));
+    }
+
+    #[test]
+    fn verify_with_validation_rejects_exp_outside_leeway() {
+        let now = time::get_time().sec as u64;
+        let payload: Payload<()> = Payload { exp: Some(now - 20), ..Default::default() };
+        let validation = Validation { leeway: 10, ..Default::default() };
+
+        assert_eq!(payload.verify_with_validation(&validation), false);
+    }
+
+    #[test]
+    fn verify_with_validation_huge_leeway_does_not_overflow() {
+        // `nbf` and `exp` both add/subtract `leeway` from `now`; a huge
+        // leeway must saturate instead of panicking on overflow in debug
+        // builds, symmetrically for both claims.
+        let now = time::get_time().sec as u64;
+        let payload: Payload<()> = Payload {
+            nbf: Some(now),
+            exp: Some(now),
+            ..Default::default()
+        };
+        let validation = Validation { leeway: u64::max_value(), ..Default::default() };
+
+        assert!(payload.verify_with_validation(&validation));
+    }
+}